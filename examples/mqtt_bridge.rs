@@ -0,0 +1,18 @@
+use std::{error::Error, time::Duration};
+
+use rumqttc::QoS;
+use ur20_modbus::mqtt::{Bridge, BridgeConfig};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let addr = "192.168.0.222:502".parse()?;
+    let config = BridgeConfig {
+        broker_url: "mqtt://localhost:1883?client_id=ur20-bridge".into(),
+        topic_prefix: "ur20".into(),
+        qos: QoS::AtLeastOnce,
+        poll_interval: Duration::from_millis(100),
+    };
+    let mut bridge = Bridge::connect(addr, config).await?;
+    bridge.run().await?;
+    Ok(())
+}
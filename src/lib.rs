@@ -24,7 +24,7 @@
 //! }
 //!```
 
-use std::{collections::HashMap, io, net::SocketAddr};
+use std::{collections::HashMap, io, net::SocketAddr, time::Duration};
 
 use tokio_modbus::{
     client::{Client as _, Context as Client},
@@ -35,6 +35,8 @@ use ur20::{
     Address, ChannelValue, ModuleType, ur20_fbc_mod_tcp::Coupler as MbCoupler, ur20_fbc_mod_tcp::*,
 };
 
+pub mod mqtt;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
@@ -47,9 +49,75 @@ pub enum Error {
     Ur20Error(#[from] ur20::Error),
     #[error("Unexpected response: {0}")]
     UnexpectedResponse(String),
+    #[error("Hardware configuration changed after reconnect")]
+    ConfigurationChanged,
+    #[error("tick() exceeded the configured watchdog timeout")]
+    Timeout,
+    #[error("Invalid scaling config: {0}")]
+    InvalidScaling(String),
+}
+
+pub(crate) type Result<T> = std::result::Result<T, Error>;
+
+/// Controls how [`Coupler`] re-establishes a lost connection.
+#[derive(Debug, Clone, Copy)]
+pub struct ReconnectPolicy {
+    /// Number of reconnect attempts before giving up.
+    pub max_attempts: u32,
+    /// Delay between two reconnect attempts.
+    pub backoff: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            max_attempts: 3,
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Linear transform applied to an analog channel to turn raw coupler
+/// values into engineering units, e.g. `23.5 °C` instead of raw counts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScalingConfig {
+    /// Factor the raw value is multiplied with.
+    pub scale: f64,
+    /// Offset added after scaling.
+    pub offset: f64,
+    /// Number of decimal places the scaled value is rounded to.
+    pub precision: u32,
 }
 
-type Result<T> = std::result::Result<T, Error>;
+/// Client certificate and private key presented for mutual TLS
+/// authentication.
+#[derive(Debug)]
+pub struct ClientAuth {
+    /// Certificate chain presented to the server.
+    pub certs: Vec<rustls::pki_types::CertificateDer<'static>>,
+    /// Private key matching the leaf certificate in `certs`.
+    pub key: rustls::pki_types::PrivateKeyDer<'static>,
+}
+
+impl Clone for ClientAuth {
+    fn clone(&self) -> Self {
+        ClientAuth {
+            certs: self.certs.clone(),
+            key: self.key.clone_key(),
+        }
+    }
+}
+
+/// Configuration for [`Coupler::connect_tls`].
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    /// Root CA store used to verify the coupler's server certificate.
+    pub root_store: rustls::RootCertStore,
+    /// Client certificate used for mutual authentication, if required.
+    pub client_auth: Option<ClientAuth>,
+    /// Server name the certificate is verified against.
+    pub server_name: String,
+}
 
 /// A Modbus TCP fieldbus coupler implementation.
 pub struct Coupler {
@@ -58,12 +126,96 @@ pub struct Coupler {
     output_count: u16,
     modules: Vec<ModuleType>,
     coupler: MbCoupler,
+    addr: SocketAddr,
+    policy: ReconnectPolicy,
+    tls: Option<TlsConfig>,
+    scaling: HashMap<Address, ScalingConfig>,
+    safe_state: Option<HashMap<Address, ChannelValue>>,
+    watchdog_timeout: Option<Duration>,
 }
 
 impl Coupler {
     /// Connect to the coupler.
     pub async fn connect(addr: SocketAddr) -> Result<Coupler> {
-        let mut ctx = tcp::connect(addr).await?;
+        Self::connect_with_policy(addr, ReconnectPolicy::default()).await
+    }
+
+    /// Connect to the coupler, using a custom [`ReconnectPolicy`] for
+    /// transparent reconnects once the connection is established.
+    pub async fn connect_with_policy(addr: SocketAddr, policy: ReconnectPolicy) -> Result<Coupler> {
+        let (client, input_count, output_count, modules, coupler) = Self::discover(addr).await?;
+        Ok(Coupler {
+            client,
+            coupler,
+            input_count,
+            output_count,
+            modules,
+            addr,
+            policy,
+            tls: None,
+            scaling: HashMap::new(),
+            safe_state: None,
+            watchdog_timeout: None,
+        })
+    }
+
+    /// Connect to the coupler over a TLS-secured Modbus/TCP session.
+    ///
+    /// The server certificate is verified against `tls.root_store`; if
+    /// `tls.client_auth` is set, it is presented for mutual authentication.
+    /// The `TlsConfig` is retained so that a later transparent reconnect
+    /// (see [`Self::tick`]) re-establishes TLS rather than silently
+    /// downgrading to a plaintext connection.
+    pub async fn connect_tls(addr: SocketAddr, tls: TlsConfig) -> Result<Coupler> {
+        let (client, input_count, output_count, modules, coupler) =
+            Self::discover_tls(addr, &tls).await?;
+        Ok(Coupler {
+            client,
+            coupler,
+            input_count,
+            output_count,
+            modules,
+            addr,
+            policy: ReconnectPolicy::default(),
+            tls: Some(tls),
+            scaling: HashMap::new(),
+            safe_state: None,
+            watchdog_timeout: None,
+        })
+    }
+
+    async fn discover(addr: SocketAddr) -> Result<(Client, u16, u16, Vec<ModuleType>, MbCoupler)> {
+        let ctx = tcp::connect(addr).await?;
+        Self::discover_on(ctx).await
+    }
+
+    async fn discover_tls(
+        addr: SocketAddr,
+        tls: &TlsConfig,
+    ) -> Result<(Client, u16, u16, Vec<ModuleType>, MbCoupler)> {
+        let tcp_stream = tokio::net::TcpStream::connect(addr).await?;
+
+        let builder = rustls::ClientConfig::builder().with_root_certificates(tls.root_store.clone());
+        let config = match &tls.client_auth {
+            Some(auth) => builder
+                .with_client_auth_cert(auth.certs.clone(), auth.key.clone_key())
+                .map_err(|err| Error::UnexpectedResponse(err.to_string()))?,
+            None => builder.with_no_client_auth(),
+        };
+
+        let server_name = rustls::pki_types::ServerName::try_from(tls.server_name.clone())
+            .map_err(|err| Error::UnexpectedResponse(err.to_string()))?;
+        let connector = tokio_rustls::TlsConnector::from(std::sync::Arc::new(config));
+        let tls_stream = connector
+            .connect(server_name, tcp_stream)
+            .await
+            .map_err(Error::IoError)?;
+
+        let ctx = tcp::attach(tls_stream);
+        Self::discover_on(ctx).await
+    }
+
+    async fn discover_on(mut ctx: Client) -> Result<(Client, u16, u16, Vec<ModuleType>, MbCoupler)> {
         let cnt = read_module_count(&mut ctx).await?;
         let modules = read_module_list(&mut ctx, cnt).await?;
         print_module_list_info(&modules);
@@ -78,14 +230,51 @@ impl Coupler {
             params,
         };
         let coupler = MbCoupler::new(&cfg)?;
-        Ok(Coupler {
-            client: ctx,
-            coupler,
-            input_count,
-            output_count,
-            modules,
-        })
+        Ok((ctx, input_count, output_count, modules, coupler))
+    }
+
+    /// Re-establish the connection and resync the module list, restoring
+    /// the pending output values if the hardware is unchanged.
+    async fn resync(&mut self) -> Result<()> {
+        log::warn!("Connection to coupler lost, trying to reconnect");
+        let pending_outputs = raw_outputs(&self.coupler);
+        let mut last_err = None;
+        for attempt in 0..self.policy.max_attempts {
+            if attempt > 0 {
+                tokio::time::sleep(self.policy.backoff).await;
+            }
+            let discovered = match &self.tls {
+                Some(tls) => Self::discover_tls(self.addr, tls).await,
+                None => Self::discover(self.addr).await,
+            };
+            match discovered {
+                Ok((client, input_count, output_count, modules, coupler)) => {
+                    if modules != self.modules {
+                        log::warn!(
+                            "Module list changed after reconnect attempt {}, retrying before giving up",
+                            attempt + 1
+                        );
+                        last_err = Some(Error::ConfigurationChanged);
+                        continue;
+                    }
+                    self.client = client;
+                    self.input_count = input_count;
+                    self.output_count = output_count;
+                    self.coupler = coupler;
+                    for (addr, value) in &pending_outputs {
+                        if let Err(err) = self.coupler.set_output(addr, value.clone()) {
+                            log::warn!("Unable to restore pending output {addr:?}: {err}");
+                        }
+                    }
+                    log::info!("Reconnected to coupler after {} attempt(s)", attempt + 1);
+                    return Ok(());
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or(Error::UnexpectedResponse("reconnect failed".into())))
     }
+
     /// Disconnect the coupler.
     pub async fn disconnect(&mut self) -> Result<()> {
         Ok(self.client.disconnect().await?)
@@ -107,6 +296,9 @@ impl Coupler {
     }
 
     /// Current input state.
+    ///
+    /// Channels with a [`ScalingConfig`] registered via [`Self::set_scaling`]
+    /// are reported in engineering units instead of raw coupler values.
     #[must_use]
     pub fn inputs(&self) -> HashMap<Address, ChannelValue> {
         self.coupler
@@ -119,21 +311,66 @@ impl Coupler {
                     .enumerate()
                     .map(move |(channel, value)| (Address { module, channel }, value))
             })
+            .map(|(addr, value)| {
+                let value = self.scale(&addr, value);
+                (addr, value)
+            })
             .collect()
     }
 
+    /// Register an engineering-unit scaling for an analog input or output
+    /// channel.
+    ///
+    /// Fails with [`Error::InvalidScaling`] if `config.scale` is zero or
+    /// not finite, which would make the raw-to-engineering-units
+    /// conversion degenerate (division by zero in [`Self::unscale`]).
+    pub fn set_scaling(&mut self, addr: Address, config: ScalingConfig) -> Result<()> {
+        validate_scaling(&config)?;
+        self.scaling.insert(addr, config);
+        Ok(())
+    }
+
+    /// Remove the scaling previously registered for `addr`, if any.
+    pub fn clear_scaling(&mut self, addr: &Address) {
+        self.scaling.remove(addr);
+    }
+
+    /// Configure the output image written on a clean shutdown of [`Self::run`]
+    /// or when the watchdog timeout is hit, e.g. all outputs zeroed.
+    ///
+    /// Values are interpreted the same way as in [`Self::set_output`]: if a
+    /// [`ScalingConfig`] is registered for a channel, its value here is
+    /// expected to be in engineering units, not raw coupler counts.
+    pub fn set_safe_state(&mut self, safe_state: HashMap<Address, ChannelValue>) {
+        self.safe_state = Some(safe_state);
+    }
+
+    /// Configure the watchdog timeout applied to `tick()` calls made from
+    /// [`Self::run`]. `None` (the default) disables the watchdog.
+    pub fn set_watchdog_timeout(&mut self, timeout: Option<Duration>) {
+        self.watchdog_timeout = timeout;
+    }
+
+    fn scale(&self, addr: &Address, value: ChannelValue) -> ChannelValue {
+        scale_value(&self.scaling, addr, value)
+    }
+
+    fn unscale(&self, addr: &Address, value: ChannelValue) -> ChannelValue {
+        unscale_value(&self.scaling, addr, value)
+    }
+
     /// Current output state.
+    ///
+    /// Channels with a [`ScalingConfig`] registered via [`Self::set_scaling`]
+    /// are reported in engineering units instead of raw coupler values, so
+    /// that reading back a value written through [`Self::set_output`] round-trips.
     #[must_use]
     pub fn outputs(&self) -> HashMap<Address, ChannelValue> {
-        self.coupler
-            .outputs()
-            .clone()
+        raw_outputs(&self.coupler)
             .into_iter()
-            .enumerate()
-            .flat_map(|(module, vals)| {
-                vals.into_iter()
-                    .enumerate()
-                    .map(move |(channel, value)| (Address { module, channel }, value))
+            .map(|(addr, value)| {
+                let value = self.scale(&addr, value);
+                (addr, value)
             })
             .collect()
     }
@@ -146,11 +383,16 @@ impl Coupler {
     }
 
     /// Set the value of an output channel.
+    ///
+    /// If a [`ScalingConfig`] is registered for `addr`, `val` is expected to
+    /// be in engineering units and is converted back to a raw value before
+    /// being handed to the coupler.
     pub fn set_output(
         &mut self,
         addr: &Address,
         val: ChannelValue,
     ) -> std::result::Result<(), ur20::Error> {
+        let val = self.unscale(addr, val);
         self.coupler.set_output(addr, val)
     }
 
@@ -219,7 +461,22 @@ impl Coupler {
     /// Run an I/O cycle.
     /// This reads all process input registers and
     /// writes to process output registers.
+    ///
+    /// If the connection was lost, this transparently reconnects
+    /// according to the configured [`ReconnectPolicy`] and retries the
+    /// cycle once. If the hardware configuration changed while
+    /// disconnected, [`Error::ConfigurationChanged`] is returned instead.
     pub async fn tick(&mut self) -> Result<()> {
+        match self.tick_once().await {
+            Err(err) if is_connection_lost(&err) => {
+                self.resync().await?;
+                self.tick_once().await
+            }
+            res => res,
+        }
+    }
+
+    async fn tick_once(&mut self) -> Result<()> {
         log::debug!("fetch data");
         let (input, output) = self.get_data().await?;
         let output = self.next_out(&input, &output)?;
@@ -227,6 +484,260 @@ impl Coupler {
         self.write(&output).await?;
         Ok(())
     }
+
+    /// Drive `tick()` on a fixed `interval` until `shutdown` is set to
+    /// `true`.
+    ///
+    /// On shutdown, the configured fail-safe output image is written (see
+    /// [`Self::set_safe_state`]) and the coupler is disconnected cleanly.
+    /// If a `tick()` call exceeds the configured watchdog timeout (see
+    /// [`Self::set_watchdog_timeout`]), the fail-safe image is written and
+    /// [`Error::Timeout`] is returned.
+    pub async fn run(
+        &mut self,
+        interval: Duration,
+        mut shutdown: tokio::sync::watch::Receiver<bool>,
+    ) -> Result<()> {
+        if *shutdown.borrow() {
+            log::info!("Shutdown already requested, writing fail-safe outputs");
+            self.fail_safe().await?;
+            self.disconnect().await?;
+            return Ok(());
+        }
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    if let Err(err) = self.guarded_tick().await {
+                        log::error!("tick() failed: {err}");
+                        if let Err(fail_safe_err) = self.fail_safe().await {
+                            log::warn!("Fail-safe write after tick() failure also failed: {fail_safe_err}");
+                        }
+                        return Err(err);
+                    }
+                }
+                res = shutdown.changed() => {
+                    if res.is_err() || *shutdown.borrow() {
+                        log::info!("Shutdown requested, writing fail-safe outputs");
+                        self.fail_safe().await?;
+                        self.disconnect().await?;
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    async fn guarded_tick(&mut self) -> Result<()> {
+        match self.watchdog_timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, self.tick()).await {
+                Ok(res) => res,
+                Err(_) => {
+                    // The timed-out call may have been cancelled mid-request/response,
+                    // leaving the shared Modbus context desynced. Force a reconnect
+                    // rather than let the next tick() reuse it as-is.
+                    log::warn!("tick() exceeded watchdog timeout of {timeout:?}, forcing resync");
+                    if let Err(err) = self.resync().await {
+                        log::warn!("Resync after watchdog timeout failed: {err}");
+                    }
+                    Err(Error::Timeout)
+                }
+            },
+            None => self.tick().await,
+        }
+    }
+
+    /// Write the configured fail-safe output image, if any, via the
+    /// existing `tick()` write path.
+    async fn fail_safe(&mut self) -> Result<()> {
+        let Some(safe_state) = self.safe_state.clone() else {
+            return Ok(());
+        };
+        for (addr, value) in safe_state {
+            if let Err(err) = self.set_output(&addr, value) {
+                log::warn!("Unable to apply fail-safe output {addr:?}: {err}");
+            }
+        }
+        let (input, output) = self.get_data().await?;
+        let output = self.next_out(&input, &output)?;
+        self.write(&output).await
+    }
+}
+
+/// Checks whether `err` was caused by a connection-reset/broken-pipe I/O
+/// error, either directly or reported by the Modbus transport, which
+/// indicates that the coupler needs to be reconnected.
+fn is_connection_lost(err: &Error) -> bool {
+    matches!(
+        err,
+        Error::IoError(io_err) | Error::ModbusError(tokio_modbus::Error::Transport(io_err))
+            if matches!(
+                io_err.kind(),
+                io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::UnexpectedEof
+            )
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connection_reset_is_detected_as_connection_lost() {
+        let err = Error::IoError(io::Error::from(io::ErrorKind::ConnectionReset));
+        assert!(is_connection_lost(&err));
+    }
+
+    #[test]
+    fn other_io_errors_are_not_connection_lost() {
+        let err = Error::IoError(io::Error::from(io::ErrorKind::NotFound));
+        assert!(!is_connection_lost(&err));
+    }
+
+    #[test]
+    fn non_io_errors_are_not_connection_lost() {
+        assert!(!is_connection_lost(&Error::ConfigurationChanged));
+    }
+
+    fn addr(module: usize, channel: usize) -> Address {
+        Address { module, channel }
+    }
+
+    #[test]
+    fn scale_and_unscale_round_trip() {
+        let mut scaling = HashMap::new();
+        scaling.insert(
+            addr(0, 0),
+            ScalingConfig {
+                scale: 2.0,
+                offset: 1.0,
+                precision: 2,
+            },
+        );
+        let scaled = scale_value(&scaling, &addr(0, 0), ChannelValue::Decimal(10.0));
+        assert_eq!(scaled, ChannelValue::Decimal(21.0));
+        let unscaled = unscale_value(&scaling, &addr(0, 0), scaled);
+        assert_eq!(unscaled, ChannelValue::Decimal(10.0));
+    }
+
+    #[test]
+    fn scale_rounds_to_configured_precision() {
+        let mut scaling = HashMap::new();
+        scaling.insert(
+            addr(1, 0),
+            ScalingConfig {
+                scale: 1.0,
+                offset: 0.0,
+                precision: 1,
+            },
+        );
+        let scaled = scale_value(&scaling, &addr(1, 0), ChannelValue::Decimal(1.2345));
+        assert_eq!(scaled, ChannelValue::Decimal(1.2));
+    }
+
+    #[test]
+    fn scale_and_unscale_are_noop_without_a_registered_config() {
+        let scaling = HashMap::new();
+        assert_eq!(
+            scale_value(&scaling, &addr(2, 0), ChannelValue::Decimal(42.0)),
+            ChannelValue::Decimal(42.0)
+        );
+        assert_eq!(
+            unscale_value(&scaling, &addr(2, 0), ChannelValue::Decimal(42.0)),
+            ChannelValue::Decimal(42.0)
+        );
+    }
+
+    #[test]
+    fn zero_scale_is_rejected() {
+        let config = ScalingConfig {
+            scale: 0.0,
+            offset: 0.0,
+            precision: 2,
+        };
+        assert!(validate_scaling(&config).is_err());
+    }
+
+    #[test]
+    fn non_finite_scale_is_rejected() {
+        let config = ScalingConfig {
+            scale: f64::NAN,
+            offset: 0.0,
+            precision: 2,
+        };
+        assert!(validate_scaling(&config).is_err());
+
+        let config = ScalingConfig {
+            scale: f64::INFINITY,
+            offset: 0.0,
+            precision: 2,
+        };
+        assert!(validate_scaling(&config).is_err());
+    }
+}
+
+fn validate_scaling(config: &ScalingConfig) -> Result<()> {
+    if config.scale == 0.0 || !config.scale.is_finite() {
+        return Err(Error::InvalidScaling(format!(
+            "scale must be non-zero and finite, got {}",
+            config.scale
+        )));
+    }
+    Ok(())
+}
+
+fn scale_value(
+    scaling: &HashMap<Address, ScalingConfig>,
+    addr: &Address,
+    value: ChannelValue,
+) -> ChannelValue {
+    let Some(cfg) = scaling.get(addr) else {
+        return value;
+    };
+    match value {
+        ChannelValue::Decimal(raw) => {
+            let scaled = f64::from(raw) * cfg.scale + cfg.offset;
+            let factor = 10f64.powi(cfg.precision as i32);
+            ChannelValue::Decimal(((scaled * factor).round() / factor) as f32)
+        }
+        other => other,
+    }
+}
+
+fn unscale_value(
+    scaling: &HashMap<Address, ScalingConfig>,
+    addr: &Address,
+    value: ChannelValue,
+) -> ChannelValue {
+    let Some(cfg) = scaling.get(addr) else {
+        return value;
+    };
+    match value {
+        ChannelValue::Decimal(val) => {
+            let raw = (f64::from(val) - cfg.offset) / cfg.scale;
+            ChannelValue::Decimal(raw as f32)
+        }
+        other => other,
+    }
+}
+
+/// Current output state of `coupler`, without any engineering-unit scaling
+/// applied.
+fn raw_outputs(coupler: &MbCoupler) -> HashMap<Address, ChannelValue> {
+    coupler
+        .outputs()
+        .clone()
+        .into_iter()
+        .enumerate()
+        .flat_map(|(module, vals)| {
+            vals.into_iter()
+                .enumerate()
+                .map(move |(channel, value)| (Address { module, channel }, value))
+        })
+        .collect()
 }
 
 async fn read_module_count(client: &mut Client) -> Result<u16> {
@@ -0,0 +1,158 @@
+//! An MQTT bridge for the [`Coupler`], turning it into a drop-in
+//! MQTT-addressable device.
+//!
+//! On every [`Bridge::run`] cycle the bridge ticks the underlying
+//! [`Coupler`], publishes every input channel that changed since the last
+//! cycle to `<prefix>/input/<module>/<channel>` and applies any queued
+//! `<prefix>/output/<module>/<channel>/set` messages to the coupler's
+//! outputs before the next tick.
+
+use std::{collections::HashMap, net::SocketAddr, time::Duration};
+
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use ur20::{Address, ChannelValue};
+
+use crate::{Coupler, Error, Result};
+
+/// Configuration for the [`Bridge`].
+#[derive(Debug, Clone)]
+pub struct BridgeConfig {
+    /// URL of the MQTT broker, including the client id, e.g.
+    /// `mqtt://localhost:1883?client_id=ur20-bridge`.
+    pub broker_url: String,
+    /// Prefix prepended to every published or subscribed topic.
+    pub topic_prefix: String,
+    /// QoS used for publishing and subscribing.
+    pub qos: QoS,
+    /// Interval between two `tick()` cycles.
+    pub poll_interval: Duration,
+}
+
+/// Bridges a [`Coupler`] to an MQTT broker.
+pub struct Bridge {
+    coupler: Coupler,
+    client: AsyncClient,
+    event_loop: rumqttc::EventLoop,
+    config: BridgeConfig,
+    last_inputs: HashMap<Address, ChannelValue>,
+}
+
+impl Bridge {
+    /// Connect to the coupler and the MQTT broker and prepare the bridge.
+    ///
+    /// Registers a retained Last-Will on `<prefix>/status` that reports
+    /// `"stopped"` if the bridge disconnects without a clean shutdown.
+    pub async fn connect(addr: SocketAddr, config: BridgeConfig) -> Result<Bridge> {
+        let coupler = Coupler::connect(addr).await?;
+
+        let mut opts = MqttOptions::parse_url(config.broker_url.clone())
+            .map_err(|err| Error::UnexpectedResponse(err.to_string()))?;
+        opts.set_last_will(rumqttc::LastWill::new(
+            status_topic(&config.topic_prefix),
+            br#"{"status":"stopped"}"#.to_vec(),
+            config.qos,
+            true,
+        ));
+
+        let (client, event_loop) = AsyncClient::new(opts, 64);
+        client
+            .subscribe(set_topic_filter(&config.topic_prefix), config.qos)
+            .await
+            .map_err(|err| Error::UnexpectedResponse(err.to_string()))?;
+        client
+            .publish(
+                status_topic(&config.topic_prefix),
+                config.qos,
+                true,
+                br#"{"status":"running"}"#.to_vec(),
+            )
+            .await
+            .map_err(|err| Error::UnexpectedResponse(err.to_string()))?;
+
+        Ok(Bridge {
+            coupler,
+            client,
+            event_loop,
+            config,
+            last_inputs: HashMap::new(),
+        })
+    }
+
+    /// Run the bridge forever, ticking the coupler on `poll_interval` and
+    /// shuttling values between the coupler and the broker.
+    ///
+    /// Incoming `.../set` messages are applied to the coupler's output
+    /// image as soon as they arrive, so that the next `tick()` writes them.
+    pub async fn run(&mut self) -> Result<()> {
+        let mut interval = tokio::time::interval(self.config.poll_interval);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    self.coupler.tick().await?;
+                    self.publish_changed_inputs().await?;
+                }
+                event = self.event_loop.poll() => {
+                    if let Ok(Event::Incoming(Packet::Publish(publish))) = event {
+                        self.handle_set_message(&publish.topic, &publish.payload);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn publish_changed_inputs(&mut self) -> Result<()> {
+        let inputs = self.coupler.inputs();
+        for (addr, value) in &inputs {
+            if self.last_inputs.get(addr) != Some(value) {
+                let payload = serde_json::to_vec(value)
+                    .map_err(|err| Error::UnexpectedResponse(err.to_string()))?;
+                self.client
+                    .publish(input_topic(&self.config.topic_prefix, addr), self.config.qos, false, payload)
+                    .await
+                    .map_err(|err| Error::UnexpectedResponse(err.to_string()))?;
+            }
+        }
+        self.last_inputs = inputs;
+        Ok(())
+    }
+
+    fn handle_set_message(&mut self, topic: &str, payload: &[u8]) {
+        let Some(addr) = parse_set_topic(&self.config.topic_prefix, topic) else {
+            log::warn!("Unexpected MQTT topic: {topic}");
+            return;
+        };
+        let value: ChannelValue = match serde_json::from_slice(payload) {
+            Ok(value) => value,
+            Err(err) => {
+                log::warn!("Invalid payload on {topic}: {err}");
+                return;
+            }
+        };
+        if let Err(err) = self.coupler.set_output(&addr, value) {
+            log::warn!("Unable to set output {addr:?}: {err}");
+        }
+    }
+}
+
+fn status_topic(prefix: &str) -> String {
+    format!("{prefix}/status")
+}
+
+fn input_topic(prefix: &str, addr: &Address) -> String {
+    format!("{prefix}/input/{}/{}", addr.module, addr.channel)
+}
+
+fn set_topic_filter(prefix: &str) -> String {
+    format!("{prefix}/output/+/+/set")
+}
+
+fn parse_set_topic(prefix: &str, topic: &str) -> Option<Address> {
+    let rest = topic
+        .strip_prefix(prefix)?
+        .strip_prefix("/output/")?
+        .strip_suffix("/set")?;
+    let mut parts = rest.splitn(2, '/');
+    let module = parts.next()?.parse().ok()?;
+    let channel = parts.next()?.parse().ok()?;
+    Some(Address { module, channel })
+}